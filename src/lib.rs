@@ -23,8 +23,10 @@
 //! of a dynamic filter in the config file will have no effect on an
 //! already-running application.
 //!
-//! This crate currently provides one dynamic filter: [`DynamicLevelFilter`],
-//! the dynamic equivalent of `ThresholdFilter`.
+//! This crate currently provides three dynamic filters: [`DynamicLevelFilter`],
+//! the dynamic equivalent of `ThresholdFilter`; [`DynamicEnvFilter`], a dynamic
+//! `env_logger`/`RUST_LOG`-style per-target level filter; and
+//! [`DynamicTargetFilter`], which allows and denies records by module-path prefix.
 //!
 //! # Example usage
 //! log4rs.yaml:
@@ -66,7 +68,8 @@ use log4rs::{
     filter::{Filter, Response},
 };
 use std::collections::HashMap;
-use std::sync::RwLock;
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::{Arc, RwLock};
 
 /// Get the default deserializers plus the ones from this module.
 pub fn default_deserializers() -> Deserializers {
@@ -78,13 +81,27 @@ pub fn default_deserializers() -> Deserializers {
 /// Add this module's deserializers to the given [`Deserializers`].
 pub fn add_deserializers(ds: &mut Deserializers) {
     ds.insert("dynamic_level", DynamicLevelFilterDeserializer);
+    ds.insert("dynamic_env", DynamicEnvFilterDeserializer);
+    ds.insert("dynamic_target", DynamicTargetFilterDeserializer);
 }
 
 lazy_static! {
-    /// Global map of all dynamic level filters.
-    static ref DYNAMIC_LEVEL_FILTERS: RwLock<HashMap<String, LevelFilter>> = RwLock::default();
+    /// Global map of all dynamic level filters. Each name maps to a shared
+    /// atomic holding the encoded [`LevelFilter`], so the filter hot path needs
+    /// neither the lock nor a hash lookup once constructed.
+    static ref DYNAMIC_LEVEL_FILTERS: RwLock<HashMap<String, Arc<AtomicU8>>> = RwLock::default();
+    /// Global map of all dynamic env filters.
+    static ref DYNAMIC_ENV_FILTERS: RwLock<HashMap<String, Vec<Directive>>> = RwLock::default();
+    /// Global map of all dynamic target filters.
+    static ref DYNAMIC_TARGET_FILTERS: RwLock<HashMap<String, TargetPrefixes>> = RwLock::default();
+    /// Change callbacks registered against dynamic level filter names.
+    static ref DYNAMIC_LEVEL_CALLBACKS: RwLock<HashMap<String, Vec<LevelChangeCallback>>> =
+        RwLock::default();
 }
 
+/// A callback invoked when a [`DynamicLevelFilter`]'s level changes at runtime.
+type LevelChangeCallback = Box<dyn Fn(LevelFilter) + Send + Sync>;
+
 /// A filter based on the log level that can be programmatically re-configured at runtime.
 /// # Configuration
 /// ```yaml
@@ -94,41 +111,113 @@ lazy_static! {
 /// # The initial log level of the filter.
 /// default: warn
 /// ```
-#[derive(Debug, Clone, Eq, PartialEq)]
+#[derive(Debug, Clone)]
 pub struct DynamicLevelFilter {
     name: String,
+    /// The shared atomic holding this filter's level, read on every record.
+    level: Arc<AtomicU8>,
+}
+
+impl PartialEq for DynamicLevelFilter {
+    fn eq(&self, other: &Self) -> bool {
+        self.name == other.name
+    }
+}
+
+impl Eq for DynamicLevelFilter {}
+
+/// Encode a [`LevelFilter`] into its `u8` discriminant for atomic storage.
+fn encode_level(level: LevelFilter) -> u8 {
+    level as u8
+}
+
+/// Decode a `u8` discriminant back into a [`LevelFilter`].
+fn decode_level(level: u8) -> LevelFilter {
+    match level {
+        0 => LevelFilter::Off,
+        1 => LevelFilter::Error,
+        2 => LevelFilter::Warn,
+        3 => LevelFilter::Info,
+        4 => LevelFilter::Debug,
+        _ => LevelFilter::Trace,
+    }
 }
 
 impl DynamicLevelFilter {
     /// Create a [`DynamicLevelFilter`] with the given name. If that name is unused,
-    /// register it and set its level to the given `starting_level`.
+    /// register it and set its level to the given `starting_level`. Filters created
+    /// with the same name share the same underlying level.
     pub fn new(name: String, starting_level: LevelFilter) -> Self {
         let mut filters = DYNAMIC_LEVEL_FILTERS.write().unwrap();
-        if !filters.contains_key(&name) {
-            let result = filters.insert(name.clone(), starting_level);
-            debug_assert!(result.is_none());
-        }
+        let level = filters
+            .entry(name.clone())
+            .or_insert_with(|| Arc::new(AtomicU8::new(encode_level(starting_level))))
+            .clone();
 
-        DynamicLevelFilter { name }
+        DynamicLevelFilter { name, level }
     }
 
     /// Set the [`DynamicLevelFilter`] with the given name to the given level.
     /// Has no effect if the name is not registered.
     pub fn set(name: &str, level: LevelFilter) {
-        let mut filters = DYNAMIC_LEVEL_FILTERS.write().unwrap();
-        if let Some(filter) = filters.get_mut(name) {
-            *filter = level;
+        Self::try_set(name, level);
+    }
+
+    /// Like [`set`](Self::set), but returns whether the name was registered.
+    /// Useful when validating remote-control requests against unknown names.
+    pub fn try_set(name: &str, level: LevelFilter) -> bool {
+        {
+            let filters = DYNAMIC_LEVEL_FILTERS.read().unwrap();
+            match filters.get(name) {
+                Some(filter) => filter.store(encode_level(level), Ordering::Relaxed),
+                None => return false,
+            }
         }
+        // Notify subscribers after the new level is stored and the filter lock
+        // is released. Callbacks run synchronously on this thread and must not
+        // call back into `set`/`try_set` for the same name.
+        let callbacks = DYNAMIC_LEVEL_CALLBACKS.read().unwrap();
+        if let Some(callbacks) = callbacks.get(name) {
+            for callback in callbacks {
+                callback(level);
+            }
+        }
+        true
+    }
+
+    /// Register a callback to be invoked whenever the [`DynamicLevelFilter`] with
+    /// the given name has its level changed via [`set`](Self::set) or
+    /// [`try_set`](Self::try_set). The callback runs synchronously on the thread
+    /// calling `set`, after the new level has been stored, and receives the new
+    /// level. It must not itself call `set`/`try_set` for the same name, which
+    /// would deadlock on the internal lock.
+    pub fn on_change(name: &str, callback: LevelChangeCallback) {
+        let mut callbacks = DYNAMIC_LEVEL_CALLBACKS.write().unwrap();
+        callbacks
+            .entry(name.to_string())
+            .or_default()
+            .push(callback);
+    }
+
+    /// Get the current level of the [`DynamicLevelFilter`] with the given name,
+    /// or [`None`] if the name is not registered.
+    pub fn get(name: &str) -> Option<LevelFilter> {
+        let filters = DYNAMIC_LEVEL_FILTERS.read().unwrap();
+        filters
+            .get(name)
+            .map(|filter| decode_level(filter.load(Ordering::Relaxed)))
+    }
+
+    /// Get a snapshot of the names of all registered [`DynamicLevelFilter`]s.
+    pub fn names() -> Vec<String> {
+        let filters = DYNAMIC_LEVEL_FILTERS.read().unwrap();
+        filters.keys().cloned().collect()
     }
 }
 
 impl Filter for DynamicLevelFilter {
     fn filter(&self, record: &Record) -> Response {
-        let level: LevelFilter = *DYNAMIC_LEVEL_FILTERS
-            .read()
-            .unwrap()
-            .get(&self.name)
-            .unwrap();
+        let level = decode_level(self.level.load(Ordering::Relaxed));
         if record.level() > level {
             Response::Reject
         } else {
@@ -164,6 +253,246 @@ impl Deserialize for DynamicLevelFilterDeserializer {
     }
 }
 
+/// A single directive from an `env_logger`/`RUST_LOG`-style string: a target
+/// prefix (empty means the global default) and the [`LevelFilter`] to apply to
+/// matching records.
+#[derive(Debug, Clone, Eq, PartialEq)]
+struct Directive {
+    target: String,
+    level: LevelFilter,
+}
+
+/// Parse an `env_logger`/`RUST_LOG`-style directive string, e.g.
+/// `"warn,my_crate::net=debug"`, into a list of [`Directive`]s. Empty and
+/// whitespace-only entries are skipped and level names are matched
+/// case-insensitively; an unparseable level name is an error.
+fn parse_directives(directives: &str) -> anyhow::Result<Vec<Directive>> {
+    let mut parsed = Vec::new();
+    for entry in directives.split(',') {
+        let entry = entry.trim();
+        if entry.is_empty() {
+            continue;
+        }
+        let (target, level) = match entry.split_once('=') {
+            Some((target, level)) => (target.trim(), level.trim()),
+            None => ("", entry),
+        };
+        let level = parse_level(level)?;
+        parsed.push(Directive {
+            target: target.to_string(),
+            level,
+        });
+    }
+    Ok(parsed)
+}
+
+/// Parse a [`LevelFilter`] case-insensitively, erroring on an unknown name.
+fn parse_level(level: &str) -> anyhow::Result<LevelFilter> {
+    level
+        .parse()
+        .map_err(|_| anyhow::anyhow!("invalid log level: {level:?}"))
+}
+
+/// A filter based on an `env_logger`/`RUST_LOG`-style set of per-target level
+/// directives, that can be programmatically re-configured at runtime.
+/// # Configuration
+/// ```yaml
+/// kind: dynamic_env
+/// # The unique name used to configure this filter at runtime.
+/// name: foo
+/// # The initial directive string, in env_logger/RUST_LOG format.
+/// default: warn,my_crate::net=debug
+/// ```
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct DynamicEnvFilter {
+    name: String,
+}
+
+impl DynamicEnvFilter {
+    /// Create a [`DynamicEnvFilter`] with the given name. If that name is unused,
+    /// register it with the given directive string, in `env_logger`/`RUST_LOG`
+    /// format. Returns an error if the directive string cannot be parsed.
+    pub fn new(name: String, directives: &str) -> anyhow::Result<Self> {
+        let directives = parse_directives(directives)?;
+        let mut filters = DYNAMIC_ENV_FILTERS.write().unwrap();
+        if !filters.contains_key(&name) {
+            let result = filters.insert(name.clone(), directives);
+            debug_assert!(result.is_none());
+        }
+
+        Ok(DynamicEnvFilter { name })
+    }
+
+    /// Set the [`DynamicEnvFilter`] with the given name to the given directive
+    /// string, in `env_logger`/`RUST_LOG` format. Has no effect if the name is
+    /// not registered. Returns an error if the directive string cannot be parsed.
+    pub fn set(name: &str, directives: &str) -> anyhow::Result<()> {
+        let directives = parse_directives(directives)?;
+        let mut filters = DYNAMIC_ENV_FILTERS.write().unwrap();
+        if let Some(filter) = filters.get_mut(name) {
+            *filter = directives;
+        }
+        Ok(())
+    }
+}
+
+impl Filter for DynamicEnvFilter {
+    fn filter(&self, record: &Record) -> Response {
+        let filters = DYNAMIC_ENV_FILTERS.read().unwrap();
+        let directives = filters.get(&self.name).unwrap();
+        let target = record.target();
+        // Select the directive whose target is the longest prefix of the
+        // record's target; an empty target matches everything as the default.
+        let level = directives
+            .iter()
+            .filter(|d| target.starts_with(&d.target))
+            .max_by_key(|d| d.target.len())
+            .map(|d| d.level)
+            .unwrap_or(LevelFilter::Off);
+        if record.level() > level {
+            Response::Reject
+        } else {
+            Response::Neutral
+        }
+    }
+}
+
+/// Configure a [`DynamicEnvFilter`] from a config file.
+#[derive(Debug, serde::Deserialize)]
+struct DynamicEnvFilterConfig {
+    name: String,
+    default: String,
+}
+
+/// Deserialize a [`DynamicEnvFilterConfig`] into a [`DynamicEnvFilter`].
+#[derive(Debug)]
+struct DynamicEnvFilterDeserializer;
+
+impl Deserialize for DynamicEnvFilterDeserializer {
+    type Trait = dyn Filter;
+    type Config = DynamicEnvFilterConfig;
+
+    fn deserialize(
+        &self,
+        config: Self::Config,
+        _: &Deserializers,
+    ) -> anyhow::Result<Box<Self::Trait>> {
+        Ok(Box::new(DynamicEnvFilter::new(
+            config.name,
+            &config.default,
+        )?))
+    }
+}
+
+/// The allow and deny prefix lists for a [`DynamicTargetFilter`]. Each list is
+/// matched with plain [`str::starts_with`] on the hot path; targets are short
+/// module paths and the lists are expected to be small, so a prefix trie would
+/// not pay for itself here.
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+struct TargetPrefixes {
+    allow: Vec<String>,
+    deny: Vec<String>,
+}
+
+/// A filter based on the record's target (module path) with runtime-editable
+/// allow and deny prefix lists, independent of level.
+/// # Configuration
+/// ```yaml
+/// kind: dynamic_target
+/// # The unique name used to configure this filter at runtime.
+/// name: foo
+/// # Module-path prefixes to reject (optional).
+/// deny:
+///   - my_crate::noisy
+/// # Module-path prefixes to allow; if non-empty, anything not matching is
+/// # rejected (optional).
+/// allow:
+///   - my_crate
+/// ```
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct DynamicTargetFilter {
+    name: String,
+}
+
+impl DynamicTargetFilter {
+    /// Create a [`DynamicTargetFilter`] with the given name. If that name is
+    /// unused, register it with the given allow and deny prefix lists.
+    pub fn new(name: String, allow: Vec<String>, deny: Vec<String>) -> Self {
+        let mut filters = DYNAMIC_TARGET_FILTERS.write().unwrap();
+        if !filters.contains_key(&name) {
+            let result = filters.insert(name.clone(), TargetPrefixes { allow, deny });
+            debug_assert!(result.is_none());
+        }
+
+        DynamicTargetFilter { name }
+    }
+
+    /// Replace the allow list of the [`DynamicTargetFilter`] with the given name.
+    /// Has no effect if the name is not registered.
+    pub fn set_allow(name: &str, allow: Vec<String>) {
+        let mut filters = DYNAMIC_TARGET_FILTERS.write().unwrap();
+        if let Some(filter) = filters.get_mut(name) {
+            filter.allow = allow;
+        }
+    }
+
+    /// Replace the deny list of the [`DynamicTargetFilter`] with the given name.
+    /// Has no effect if the name is not registered.
+    pub fn set_deny(name: &str, deny: Vec<String>) {
+        let mut filters = DYNAMIC_TARGET_FILTERS.write().unwrap();
+        if let Some(filter) = filters.get_mut(name) {
+            filter.deny = deny;
+        }
+    }
+}
+
+impl Filter for DynamicTargetFilter {
+    fn filter(&self, record: &Record) -> Response {
+        let filters = DYNAMIC_TARGET_FILTERS.read().unwrap();
+        let prefixes = filters.get(&self.name).unwrap();
+        let target = record.target();
+        if prefixes.deny.iter().any(|p| target.starts_with(p)) {
+            Response::Reject
+        } else if prefixes.allow.is_empty() || prefixes.allow.iter().any(|p| target.starts_with(p))
+        {
+            Response::Neutral
+        } else {
+            Response::Reject
+        }
+    }
+}
+
+/// Configure a [`DynamicTargetFilter`] from a config file.
+#[derive(Debug, serde::Deserialize)]
+struct DynamicTargetFilterConfig {
+    name: String,
+    #[serde(default)]
+    allow: Vec<String>,
+    #[serde(default)]
+    deny: Vec<String>,
+}
+
+/// Deserialize a [`DynamicTargetFilterConfig`] into a [`DynamicTargetFilter`].
+#[derive(Debug)]
+struct DynamicTargetFilterDeserializer;
+
+impl Deserialize for DynamicTargetFilterDeserializer {
+    type Trait = dyn Filter;
+    type Config = DynamicTargetFilterConfig;
+
+    fn deserialize(
+        &self,
+        config: Self::Config,
+        _: &Deserializers,
+    ) -> anyhow::Result<Box<Self::Trait>> {
+        Ok(Box::new(DynamicTargetFilter::new(
+            config.name,
+            config.allow,
+            config.deny,
+        )))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -199,6 +528,14 @@ mod tests {
         test_setup(filter)
     }
 
+    fn test_setup_dynamic_env(
+        name: String,
+        directives: &str,
+    ) -> (MutexGuard<'static, ()>, LogsHandle) {
+        let filter = Box::new(DynamicEnvFilter::new(name, directives).unwrap());
+        test_setup(filter)
+    }
+
     #[test]
     fn dlf_default() {
         let (_guard, logs_handle) =
@@ -237,4 +574,138 @@ mod tests {
             assert!(line.contains("Seen!"));
         }
     }
+
+    #[test]
+    fn dlf_introspection() {
+        let _filter = DynamicLevelFilter::new("dlf_introspection".to_string(), LevelFilter::Info);
+
+        assert_eq!(
+            DynamicLevelFilter::get("dlf_introspection"),
+            Some(LevelFilter::Info)
+        );
+        assert_eq!(DynamicLevelFilter::get("dlf_nonexistent"), None);
+        assert!(DynamicLevelFilter::names().contains(&"dlf_introspection".to_string()));
+
+        assert!(DynamicLevelFilter::try_set(
+            "dlf_introspection",
+            LevelFilter::Warn
+        ));
+        assert!(!DynamicLevelFilter::try_set(
+            "dlf_nonexistent",
+            LevelFilter::Warn
+        ));
+        assert_eq!(
+            DynamicLevelFilter::get("dlf_introspection"),
+            Some(LevelFilter::Warn)
+        );
+    }
+
+    #[test]
+    fn dlf_on_change() {
+        use std::sync::atomic::AtomicU8;
+        use std::sync::Arc;
+
+        let _filter = DynamicLevelFilter::new("dlf_on_change".to_string(), LevelFilter::Info);
+        let seen = Arc::new(AtomicU8::new(0));
+        let seen2 = seen.clone();
+        DynamicLevelFilter::on_change(
+            "dlf_on_change",
+            Box::new(move |level| seen2.store(encode_level(level), Ordering::Relaxed)),
+        );
+
+        DynamicLevelFilter::set("dlf_on_change", LevelFilter::Trace);
+        assert_eq!(
+            decode_level(seen.load(Ordering::Relaxed)),
+            LevelFilter::Trace
+        );
+    }
+
+    #[test]
+    fn def_per_target() {
+        let (_guard, logs_handle) = test_setup_dynamic_env(
+            "def_per_target".to_string(),
+            "warn,log4rs_dynamic_filters=trace",
+        );
+
+        // The global default of `warn` rejects this info record from another target.
+        info!(target: "other", "Hidden!");
+        // This crate's target is allowed down to `trace`.
+        trace!("Seen!");
+        error!(target: "other", "Seen!");
+
+        let logs = logs_handle.lock().unwrap();
+        assert_eq!(logs.len(), 2);
+        for line in logs.iter() {
+            assert!(line.contains("Seen!"));
+        }
+    }
+
+    fn test_setup_dynamic_target(
+        name: String,
+        allow: Vec<String>,
+        deny: Vec<String>,
+    ) -> (MutexGuard<'static, ()>, LogsHandle) {
+        let filter = Box::new(DynamicTargetFilter::new(name, allow, deny));
+        test_setup(filter)
+    }
+
+    #[test]
+    fn dtf_deny() {
+        let (_guard, logs_handle) = test_setup_dynamic_target(
+            "dtf_deny".to_string(),
+            vec![],
+            vec!["noisy".to_string()],
+        );
+
+        error!(target: "noisy", "Hidden!");
+        error!(target: "quiet", "Seen!");
+        DynamicTargetFilter::set_deny("dtf_deny", vec!["quiet".to_string()]);
+        error!(target: "quiet", "Hidden!");
+        error!(target: "noisy", "Seen!");
+
+        let logs = logs_handle.lock().unwrap();
+        assert_eq!(logs.len(), 2);
+        for line in logs.iter() {
+            assert!(line.contains("Seen!"));
+        }
+    }
+
+    #[test]
+    fn dtf_allow() {
+        let (_guard, logs_handle) = test_setup_dynamic_target(
+            "dtf_allow".to_string(),
+            vec!["spotlight".to_string()],
+            vec![],
+        );
+
+        error!(target: "spotlight::inner", "Seen!");
+        error!(target: "other", "Hidden!");
+
+        let logs = logs_handle.lock().unwrap();
+        assert_eq!(logs.len(), 1);
+        assert!(logs[0].contains("Seen!"));
+    }
+
+    #[test]
+    fn def_change() {
+        let (_guard, logs_handle) =
+            test_setup_dynamic_env("def_change".to_string(), "error");
+
+        info!("Hidden!");
+        DynamicEnvFilter::set("def_change", "info").unwrap();
+        info!("Seen!");
+
+        let logs = logs_handle.lock().unwrap();
+        assert_eq!(logs.len(), 1);
+        assert!(logs[0].contains("Seen!"));
+    }
+
+    #[test]
+    fn def_parse_errors() {
+        assert!(parse_directives("warn,foo=debug").is_ok());
+        assert!(parse_directives("  ,warn,  ").is_ok());
+        assert!(parse_directives("WARN,foo=TRACE").is_ok());
+        assert!(parse_directives("notalevel").is_err());
+        assert!(parse_directives("foo=notalevel").is_err());
+    }
 }